@@ -0,0 +1,156 @@
+use std::fs::OpenOptions;
+use std::io::{self, BufRead, BufReader, Write};
+
+/// Reads the `numprocs=` value out of a Supervisor program section file.
+pub async fn get_current_num_procs(path: &str) -> io::Result<usize> {
+    let file = OpenOptions::new().read(true).open(path)?;
+    let reader = BufReader::new(file);
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.starts_with("numprocs=") {
+            let parts: Vec<&str> = line.split('=').collect();
+            if parts.len() == 2 {
+                if let Ok(num_procs) = parts[1].trim().parse::<usize>() {
+                    return Ok(num_procs);
+                }
+            }
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "numprocs not found",
+    ))
+}
+
+/// Prefix for the hidden comment `update_supervisor_config` uses to keep a
+/// `command=` line's original `%(numprocs)d` template around after the
+/// placeholder itself has been expanded into a literal number, so the next
+/// update can re-expand it too instead of being stuck with whatever digit
+/// ended up there the first time.
+const COMMAND_TEMPLATE_PREFIX: &str = "# qscaler:command_template=";
+
+/// Rewrites the `numprocs=` line in a Supervisor program section file so a
+/// subsequent `supervisor.reloadConfig` picks up the new process count,
+/// re-expanding any `command=` line that references `%(numprocs)d` against
+/// the new value on every call, not just the first. Every other line,
+/// including comments, is left untouched and in place.
+pub async fn update_supervisor_config(num_procs: usize, path: &str) -> io::Result<()> {
+    let file = OpenOptions::new().read(true).open(path)?;
+    let reader = BufReader::new(file);
+    let mut lines: Vec<String> = Vec::new();
+    for line in reader.lines() {
+        lines.push(line?);
+    }
+
+    // The canonical command= line with %(numprocs)d still in it: either
+    // captured by a prior update into a hidden comment, or, the first time
+    // numprocs changes, read straight off the still-templated command= line.
+    let template = lines
+        .iter()
+        .find_map(|line| line.strip_prefix(COMMAND_TEMPLATE_PREFIX))
+        .map(str::to_string)
+        .or_else(|| {
+            lines
+                .iter()
+                .find(|line| line.starts_with("command=") && line.contains("%(numprocs)d"))
+                .cloned()
+        });
+
+    let mut saw_template_comment = false;
+    let mut out: Vec<String> = Vec::with_capacity(lines.len() + 1);
+    for line in lines {
+        if line.starts_with(COMMAND_TEMPLATE_PREFIX) {
+            saw_template_comment = true;
+            out.push(line);
+        } else if line.starts_with("numprocs=") {
+            out.push(format!("numprocs={}", num_procs));
+        } else if line.starts_with("command=") {
+            match &template {
+                Some(template) => out.push(template.replace("%(numprocs)d", &num_procs.to_string())),
+                None => out.push(line),
+            }
+        } else {
+            out.push(line);
+        }
+    }
+
+    if !saw_template_comment {
+        if let Some(template) = &template {
+            out.push(format!("{COMMAND_TEMPLATE_PREFIX}{template}"));
+        }
+    }
+
+    let mut file = OpenOptions::new().write(true).truncate(true).open(path)?;
+    for line in out {
+        writeln!(file, "{}", line)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use tokio::fs;
+
+    #[tokio::test]
+    async fn test_get_current_num_procs() {
+        let test_config_path = "example/sample.conf";
+        let mut file = File::create(test_config_path).unwrap();
+        writeln!(file, "numprocs=5").unwrap();
+
+        let num_procs = get_current_num_procs(test_config_path).await.unwrap();
+        assert_eq!(num_procs, 5);
+
+        fs::remove_file(test_config_path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_update_supervisor_config() {
+        let test_config_path = "test_supervisor.conf";
+        let mut file = File::create(test_config_path).unwrap();
+        writeln!(file, "numprocs=5").unwrap();
+
+        update_supervisor_config(10, test_config_path).await.unwrap();
+
+        let num_procs = get_current_num_procs(test_config_path).await.unwrap();
+        assert_eq!(num_procs, 10);
+    }
+
+    #[tokio::test]
+    async fn test_update_supervisor_config_expands_numprocs_in_command() {
+        let test_config_path = "test_supervisor_command.conf";
+        let mut file = File::create(test_config_path).unwrap();
+        writeln!(file, "# worker pool").unwrap();
+        writeln!(file, "numprocs=5").unwrap();
+        writeln!(file, "command=/usr/bin/worker --shard %(process_num)d/%(numprocs)d").unwrap();
+
+        update_supervisor_config(10, test_config_path).await.unwrap();
+
+        let contents = tokio::fs::read_to_string(test_config_path).await.unwrap();
+        assert!(contents.contains("# worker pool"));
+        assert!(contents.contains("numprocs=10"));
+        assert!(contents.contains("command=/usr/bin/worker --shard %(process_num)d/10"));
+
+        fs::remove_file(test_config_path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_update_supervisor_config_re_expands_command_on_every_call() {
+        let test_config_path = "test_supervisor_command_repeat.conf";
+        let mut file = File::create(test_config_path).unwrap();
+        writeln!(file, "numprocs=5").unwrap();
+        writeln!(file, "command=/usr/bin/worker --shard %(process_num)d/%(numprocs)d").unwrap();
+
+        update_supervisor_config(10, test_config_path).await.unwrap();
+        update_supervisor_config(20, test_config_path).await.unwrap();
+
+        let contents = tokio::fs::read_to_string(test_config_path).await.unwrap();
+        assert!(contents.contains("numprocs=20"));
+        assert!(contents.contains("command=/usr/bin/worker --shard %(process_num)d/20"));
+        assert!(!contents.contains("command=/usr/bin/worker --shard %(process_num)d/10"));
+
+        fs::remove_file(test_config_path).await.unwrap();
+    }
+}
@@ -0,0 +1,114 @@
+use crate::worker::Worker;
+use std::time::{Duration, SystemTime};
+use tokio::sync::{mpsc, oneshot};
+
+/// Whether the supervised worker is currently ticking, paused, or has
+/// exited after receiving `Cancel`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WorkerState {
+    Active,
+    Idle,
+    Dead,
+}
+
+/// A snapshot of what the supervised worker is doing, returned in
+/// response to `Command::Status`.
+#[derive(Clone, Debug)]
+pub struct Status {
+    pub state: WorkerState,
+    pub last_target: Option<usize>,
+    pub last_error: Option<String>,
+    pub last_scale_at: Option<SystemTime>,
+}
+
+/// Commands accepted by a `WorkerSupervisor` over its control channel.
+pub enum Command {
+    Pause,
+    Resume,
+    Cancel,
+    Status(oneshot::Sender<Status>),
+}
+
+/// Drives a `Worker`'s `tick` on an interval, honoring `Pause`/`Resume`/
+/// `Cancel` commands sent over an mpsc channel and tracking enough state
+/// to answer `Status` queries. This makes an otherwise fire-and-forget
+/// loop observable and controllable: an operator can ask "what is
+/// qscaler doing right now" and temporarily freeze scaling during a
+/// deploy without killing the process.
+pub struct WorkerSupervisor<W: Worker> {
+    worker: W,
+    tick_interval: Duration,
+    commands: mpsc::Receiver<Command>,
+    state: WorkerState,
+    last_target: Option<usize>,
+    last_error: Option<String>,
+    last_scale_at: Option<SystemTime>,
+}
+
+impl<W: Worker> WorkerSupervisor<W> {
+    pub fn new(worker: W, tick_interval: Duration) -> (Self, mpsc::Sender<Command>) {
+        let (tx, rx) = mpsc::channel(16);
+        (
+            Self {
+                worker,
+                tick_interval,
+                commands: rx,
+                state: WorkerState::Active,
+                last_target: None,
+                last_error: None,
+                last_scale_at: None,
+            },
+            tx,
+        )
+    }
+
+    fn status(&self) -> Status {
+        Status {
+            state: self.state,
+            last_target: self.last_target,
+            last_error: self.last_error.clone(),
+            last_scale_at: self.last_scale_at,
+        }
+    }
+
+    /// Runs until a `Cancel` command is received, ticking the worker on
+    /// `tick_interval` while `Active` and skipping ticks while `Idle`.
+    pub async fn run(mut self) {
+        let mut ticker = tokio::time::interval(self.tick_interval);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    if self.state != WorkerState::Active {
+                        continue;
+                    }
+                    match self.worker.tick().await {
+                        Ok(Some(target)) => {
+                            self.last_error = None;
+                            self.last_target = Some(target);
+                            self.last_scale_at = Some(SystemTime::now());
+                        }
+                        Ok(None) => {
+                            self.last_error = None;
+                        }
+                        Err(err) => {
+                            self.last_error = Some(err.to_string());
+                        }
+                    }
+                }
+                command = self.commands.recv() => {
+                    match command {
+                        Some(Command::Pause) => self.state = WorkerState::Idle,
+                        Some(Command::Resume) => self.state = WorkerState::Active,
+                        Some(Command::Cancel) | None => {
+                            self.state = WorkerState::Dead;
+                            break;
+                        }
+                        Some(Command::Status(reply)) => {
+                            let _ = reply.send(self.status());
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::control::{Command, Status};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixListener;
+use tokio::sync::{mpsc, oneshot};
+
+/// Listens on a Unix domain socket for line-based control commands
+/// (`pause`, `resume`, `cancel`, `status`) and forwards them to the
+/// supervised worker over `commands`, so an operator can inspect or
+/// freeze scaling with e.g. `echo status | nc -U <socket_path>`.
+pub async fn serve(socket_path: &str, commands: mpsc::Sender<Command>) -> std::io::Result<()> {
+    let _ = std::fs::remove_file(socket_path);
+    let listener = UnixListener::bind(socket_path)?;
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let commands = commands.clone();
+        tokio::spawn(async move {
+            let (reader, mut writer) = stream.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let response = handle_line(&line, &commands).await;
+                if writer.write_all(response.as_bytes()).await.is_err()
+                    || writer.write_all(b"\n").await.is_err()
+                {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+async fn handle_line(line: &str, commands: &mpsc::Sender<Command>) -> String {
+    match line.trim() {
+        "pause" => send(commands, Command::Pause).await,
+        "resume" => send(commands, Command::Resume).await,
+        "cancel" => send(commands, Command::Cancel).await,
+        "status" => {
+            let (tx, rx) = oneshot::channel();
+            if commands.send(Command::Status(tx)).await.is_err() {
+                return "error: worker not running".to_string();
+            }
+            match rx.await {
+                Ok(status) => format_status(&status),
+                Err(_) => "error: no response from worker".to_string(),
+            }
+        }
+        other => format!("error: unknown command '{}'", other),
+    }
+}
+
+async fn send(commands: &mpsc::Sender<Command>, command: Command) -> String {
+    match commands.send(command).await {
+        Ok(()) => "ok".to_string(),
+        Err(_) => "error: worker not running".to_string(),
+    }
+}
+
+fn format_status(status: &Status) -> String {
+    format!(
+        "state={:?} last_target={:?} last_error={:?} last_scale_at={:?}",
+        status.state, status.last_target, status.last_error, status.last_scale_at
+    )
+}
@@ -0,0 +1,212 @@
+use std::fs;
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Debounces the raw `queue_length / scale_factor` target to stop process
+/// flapping: a newly computed target must hold for `required_consecutive_ticks`
+/// ticks before it's acted on, separate cooldowns gate scale-up vs
+/// scale-down, and scale-down moves at most one step per cooldown while
+/// scale-up is applied in full immediately. The last applied target and
+/// its timestamp are persisted to `state_path` so a restart doesn't
+/// immediately re-scale.
+pub struct ScaleController {
+    scale_up_cooldown: Duration,
+    scale_down_cooldown: Duration,
+    required_consecutive_ticks: u32,
+    state_path: String,
+    last_target: usize,
+    last_scale_at: Option<SystemTime>,
+    pending_target: Option<usize>,
+    pending_count: u32,
+}
+
+impl ScaleController {
+    pub fn new(
+        scale_up_cooldown: Duration,
+        scale_down_cooldown: Duration,
+        required_consecutive_ticks: u32,
+        state_path: String,
+        initial_target: usize,
+    ) -> Self {
+        let (last_target, last_scale_at) =
+            load_state(&state_path).unwrap_or((initial_target, None));
+        Self {
+            scale_up_cooldown,
+            scale_down_cooldown,
+            required_consecutive_ticks,
+            state_path,
+            last_target,
+            last_scale_at,
+            pending_target: None,
+            pending_count: 0,
+        }
+    }
+
+    /// The target this controller last applied (or loaded from disk).
+    pub fn current_target(&self) -> usize {
+        self.last_target
+    }
+
+    /// Feeds in a freshly computed target and returns the process count
+    /// that should actually be applied this tick, or `None` if the
+    /// decision should hold for now.
+    pub fn decide(&mut self, computed_target: usize) -> Option<usize> {
+        if computed_target == self.last_target {
+            self.pending_target = None;
+            self.pending_count = 0;
+            return None;
+        }
+
+        if self.pending_target != Some(computed_target) {
+            self.pending_target = Some(computed_target);
+            self.pending_count = 1;
+        } else {
+            self.pending_count += 1;
+        }
+
+        if self.pending_count < self.required_consecutive_ticks {
+            return None;
+        }
+
+        let scaling_up = computed_target > self.last_target;
+        let cooldown = if scaling_up {
+            self.scale_up_cooldown
+        } else {
+            self.scale_down_cooldown
+        };
+        if let Some(last_scale_at) = self.last_scale_at {
+            if let Ok(elapsed) = SystemTime::now().duration_since(last_scale_at) {
+                if elapsed < cooldown {
+                    return None;
+                }
+            }
+        }
+
+        let next_target = if scaling_up {
+            computed_target
+        } else {
+            // Scale down by at most one step per cooldown.
+            self.last_target.saturating_sub(1).max(computed_target)
+        };
+
+        self.pending_target = None;
+        self.pending_count = 0;
+        self.record(next_target);
+        Some(next_target)
+    }
+
+    /// Restores the controller to `target` without waiting out a cooldown,
+    /// used to undo `decide`'s bookkeeping when applying its result fails.
+    pub fn revert(&mut self, target: usize) {
+        self.record(target);
+    }
+
+    fn record(&mut self, target: usize) {
+        self.last_target = target;
+        self.last_scale_at = Some(SystemTime::now());
+        if let Err(err) = self.persist() {
+            eprintln!(
+                "Failed to persist scaling state to {}: {}",
+                self.state_path, err
+            );
+        }
+    }
+
+    fn persist(&self) -> io::Result<()> {
+        let since_epoch = self
+            .last_scale_at
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        fs::write(
+            &self.state_path,
+            format!("{}\n{}\n", self.last_target, since_epoch),
+        )
+    }
+}
+
+fn load_state(path: &str) -> Option<(usize, Option<SystemTime>)> {
+    let contents = fs::read_to_string(path).ok()?;
+    let mut lines = contents.lines();
+    let target = lines.next()?.trim().parse::<usize>().ok()?;
+    let since_epoch = lines.next().and_then(|line| line.trim().parse::<u64>().ok());
+    let last_scale_at = since_epoch
+        .filter(|&secs| secs > 0)
+        .map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+    Some((target, last_scale_at))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_cooldown_controller(name: &str, initial_target: usize) -> ScaleController {
+        let state_path = format!("/tmp/qscaler_controller_test_{name}.state");
+        // Drop state persisted by an earlier run so `initial_target` applies.
+        let _ = fs::remove_file(&state_path);
+        ScaleController::new(Duration::ZERO, Duration::ZERO, 1, state_path, initial_target)
+    }
+
+    #[test]
+    fn test_unchanged_target_does_nothing() {
+        let mut controller = no_cooldown_controller("unchanged", 3);
+        assert_eq!(controller.decide(3), None);
+    }
+
+    #[test]
+    fn test_scale_up_applies_in_full_immediately() {
+        let mut controller = no_cooldown_controller("scale_up", 2);
+        assert_eq!(controller.decide(10), Some(10));
+        assert_eq!(controller.current_target(), 10);
+    }
+
+    #[test]
+    fn test_scale_down_moves_one_step_at_a_time() {
+        let mut controller = no_cooldown_controller("scale_down", 10);
+        assert_eq!(controller.decide(2), Some(9));
+        assert_eq!(controller.decide(2), Some(8));
+    }
+
+    #[test]
+    fn test_requires_consecutive_ticks_before_acting() {
+        let _ = fs::remove_file("/tmp/qscaler_controller_test_consecutive.state");
+        let mut controller = ScaleController::new(
+            Duration::ZERO,
+            Duration::ZERO,
+            3,
+            "/tmp/qscaler_controller_test_consecutive.state".to_string(),
+            5,
+        );
+        assert_eq!(controller.decide(8), None);
+        assert_eq!(controller.decide(8), None);
+        assert_eq!(controller.decide(8), Some(8));
+    }
+
+    #[test]
+    fn test_flapping_target_resets_consecutive_count() {
+        let mut controller = ScaleController::new(
+            Duration::ZERO,
+            Duration::ZERO,
+            2,
+            "/tmp/qscaler_controller_test_flapping.state".to_string(),
+            5,
+        );
+        assert_eq!(controller.decide(8), None);
+        assert_eq!(controller.decide(5), None);
+        assert_eq!(controller.decide(8), None);
+    }
+
+    #[test]
+    fn test_scale_up_cooldown_blocks_back_to_back_scaling() {
+        let _ = fs::remove_file("/tmp/qscaler_controller_test_cooldown.state");
+        let mut controller = ScaleController::new(
+            Duration::from_secs(3600),
+            Duration::ZERO,
+            1,
+            "/tmp/qscaler_controller_test_cooldown.state".to_string(),
+            1,
+        );
+        assert_eq!(controller.decide(5), Some(5));
+        assert_eq!(controller.decide(9), None);
+    }
+}
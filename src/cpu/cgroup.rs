@@ -0,0 +1,142 @@
+use super::{CpuProvider, HostCpuProvider};
+use async_trait::async_trait;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::time::Duration;
+use tokio::time::sleep;
+
+const DEFAULT_CGROUP_PATH: &str = "/sys/fs/cgroup";
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Reads true in-container CPU utilization from the cgroup v2 hierarchy:
+/// `cpu.stat`'s `usage_usec` is sampled twice over a short interval, and
+/// the delta is divided by the effective quota derived from `cpu.max`
+/// (`quota/period` cores). Falls back to host-global usage on cgroup v1
+/// (no `cpu.stat`/`usage_usec`) or when no quota is set (`cpu.max` reads
+/// `max <period>`), where "percent of the container's slice" is
+/// meaningless.
+pub struct CgroupCpuProvider {
+    cgroup_path: String,
+}
+
+impl Default for CgroupCpuProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CgroupCpuProvider {
+    pub fn new() -> Self {
+        Self {
+            cgroup_path: DEFAULT_CGROUP_PATH.to_string(),
+        }
+    }
+
+    fn cpu_stat_path(&self) -> String {
+        format!("{}/cpu.stat", self.cgroup_path)
+    }
+
+    fn cpu_max_path(&self) -> String {
+        format!("{}/cpu.max", self.cgroup_path)
+    }
+
+    fn read_usage_usec(&self) -> io::Result<u64> {
+        let stat = fs::read_to_string(self.cpu_stat_path())?;
+        for line in stat.lines() {
+            if let Some(value) = line.strip_prefix("usage_usec ") {
+                return value
+                    .trim()
+                    .parse::<u64>()
+                    .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "bad usage_usec"));
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            "usage_usec not found in cpu.stat",
+        ))
+    }
+
+    /// The number of cores this cgroup is allowed to use, or `None` if no
+    /// quota is set (`cpu.max` reads `max <period>`).
+    fn read_quota_cores(&self) -> io::Result<Option<f64>> {
+        let contents = fs::read_to_string(self.cpu_max_path())?;
+        let mut parts = contents.split_whitespace();
+        let quota = parts
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "empty cpu.max"))?;
+        if quota == "max" {
+            return Ok(None);
+        }
+        let period = parts
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing period in cpu.max"))?;
+        let quota: f64 = quota
+            .parse()
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "bad quota"))?;
+        let period: f64 = period
+            .trim()
+            .parse()
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "bad period"))?;
+        Ok(Some(quota / period))
+    }
+
+    async fn sampled_usage(&self) -> Result<f32, Box<dyn Error>> {
+        let quota_cores = self
+            .read_quota_cores()?
+            .ok_or("no CPU quota set for this cgroup")?;
+
+        let start = self.read_usage_usec()?;
+        sleep(SAMPLE_INTERVAL).await;
+        let end = self.read_usage_usec()?;
+
+        let delta_usec = end.saturating_sub(start) as f64;
+        let interval_usec = SAMPLE_INTERVAL.as_micros() as f64;
+        Ok(((delta_usec / (interval_usec * quota_cores)) * 100.0) as f32)
+    }
+}
+
+#[async_trait]
+impl CpuProvider for CgroupCpuProvider {
+    async fn usage(&self) -> f32 {
+        match self.sampled_usage().await {
+            Ok(usage) => usage,
+            Err(err) => {
+                eprintln!(
+                    "cgroup CPU usage unavailable ({}), falling back to host usage",
+                    err
+                );
+                HostCpuProvider.usage().await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_quota_cores_unlimited() {
+        let dir = std::env::temp_dir().join("qscaler_cgroup_test_unlimited");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("cpu.max"), "max 100000\n").unwrap();
+
+        let provider = CgroupCpuProvider {
+            cgroup_path: dir.to_string_lossy().to_string(),
+        };
+        assert_eq!(provider.read_quota_cores().unwrap(), None);
+    }
+
+    #[test]
+    fn test_read_quota_cores_limited() {
+        let dir = std::env::temp_dir().join("qscaler_cgroup_test_limited");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("cpu.max"), "200000 100000\n").unwrap();
+
+        let provider = CgroupCpuProvider {
+            cgroup_path: dir.to_string_lossy().to_string(),
+        };
+        assert_eq!(provider.read_quota_cores().unwrap(), Some(2.0));
+    }
+}
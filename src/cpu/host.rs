@@ -0,0 +1,32 @@
+use super::CpuProvider;
+use async_trait::async_trait;
+use sysinfo::{CpuRefreshKind, RefreshKind, System};
+use tokio::time::sleep;
+
+/// Reads host-global CPU usage via `sysinfo`. Inside a container this is
+/// misleading, since it reflects every core on the host rather than the
+/// container's own quota.
+pub struct HostCpuProvider;
+
+#[async_trait]
+impl CpuProvider for HostCpuProvider {
+    async fn usage(&self) -> f32 {
+        let mut system = System::new_with_specifics(
+            RefreshKind::default().with_cpu(CpuRefreshKind::everything()),
+        );
+        sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL).await;
+        system.refresh_cpu_usage();
+        system.global_cpu_usage()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_host_cpu_provider_usage() {
+        let usage = HostCpuProvider.usage().await;
+        assert!(usage >= 0.0 && usage <= 100.0);
+    }
+}
@@ -0,0 +1,16 @@
+mod cgroup;
+mod host;
+
+pub use cgroup::CgroupCpuProvider;
+pub use host::HostCpuProvider;
+
+use async_trait::async_trait;
+
+/// A source of the current CPU utilization percentage, gating whether
+/// `ScalingWorker` is allowed to scale this tick.
+#[async_trait]
+pub trait CpuProvider: Send + Sync {
+    /// Returns CPU usage as a percentage (0.0-100.0, though a cgroup under
+    /// heavy throttling can briefly exceed 100.0 across multiple cores).
+    async fn usage(&self) -> f32;
+}
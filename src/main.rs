@@ -1,323 +1,523 @@
 use aws_config::BehaviorVersion;
-/// This Rust program is designed to scale the number of processes managed by Supervisor based on CPU usage and the length of an AWS SQS queue.
+/// This Rust program is designed to scale the number of processes managed by Supervisor based on CPU usage and the backlog reported by a configurable queue/metric backend.
 ///
 /// The main components of the program are:
 ///
-/// - `get_cpu_usage`: Asynchronously retrieves the current CPU usage of the system.
-/// - `get_sqs_queue_length`: Asynchronously retrieves the length of the specified SQS queue.
-/// - `get_current_num_procs`: Reads the current number of processes from the Supervisor configuration file.
-/// - `update_supervisor_config`: Updates the number of processes in the Supervisor configuration file.
-/// - `reload_supervisor`: Reloads the Supervisor configuration to apply changes.
-/// - `scaling_loop`: The main loop that periodically checks CPU usage and queue length, and scales the number of processes accordingly.
+/// - `cpu::CpuProvider`: Trait abstracting over how CPU usage is measured (host-global via `sysinfo`, or cgroup v2 aware).
+/// - `source::ScalingSource`: Trait abstracting over the queue/metric backend (SQS, Kafka, ...) that reports backlog size.
+/// - `config::get_current_num_procs`: Reads the current number of processes from a Supervisor program's configuration file.
+/// - `config::update_supervisor_config`: Updates the number of processes in a Supervisor program's configuration file.
+/// - `supervisor::SupervisorFleet`: Talks to one or more Supervisor instances over XML-RPC to reload config and start/stop processes.
+/// - `controller::ScaleController`: Debounces the raw target with cooldowns and a consecutive-tick requirement to stop flapping.
+/// - `worker::Worker` / `ScalingWorker`: One scaling decision, wrapped so it can be driven a tick at a time.
+/// - `control::WorkerSupervisor`: Ticks the worker on an interval and answers `Pause`/`Resume`/`Cancel`/`Status` commands.
+/// - `control_socket`: Exposes those commands over a local Unix socket so an operator can observe and control a running qscaler.
 ///
 /// The program is configured using command-line arguments:
 ///
 /// - `scale_factor`: The message count threshold to trigger scaling.
 /// - `min_num_process`: The minimum number of processes to maintain.
 /// - `max_num_process`: The maximum number of processes to maintain.
-/// - `supervisor_config_path`: The path to the Supervisor configuration file.
-/// - `queue_url`: The URL of the SQS queue.
+/// - `program`: The name of the Supervisor program/process group being scaled.
+/// - `supervisor`: One or more `host:port=config_path` Supervisor XML-RPC endpoints to distribute processes across, each with its own on-disk program config.
+/// - `source`: Which backend to pull backlog from (`sqs` or `kafka`), plus that backend's own flags.
+/// - `control_socket`: Path to the Unix control socket an operator can send `pause`/`resume`/`cancel`/`status` to.
+/// - `cpu_source`: Which CPU usage provider to gate scaling on (`host` or `cgroup`).
+/// - `scale_up_cooldown` / `scale_down_cooldown` / `consecutive_ticks`: Debounce knobs for the scaling controller.
+/// - `state_path`: Where the controller persists its last applied target across restarts.
+/// - `max_backlog_age`: How long the oldest message can wait before the target is sized for visible plus in-flight messages instead of visible messages alone.
+/// - `stop_signal` / `stop_timeout`: The signal sent to processes being removed on scale-down, and how long to let them drain before force-stopping them.
+/// - `rules_file`: Path to a TOML file describing several program sections (see `rules::ProgramRule`), each scaled by its own `ScalingWorker`, instead of the single program named on the command line.
 ///
-/// The program runs indefinitely, periodically checking the CPU usage and queue length, and adjusting the number of processes as needed.
+/// The program runs indefinitely, periodically checking the CPU usage and backlog, and adjusting the number of processes as needed.
 ///
 /// # Usage
 ///
 /// ```sh
-/// cargo run -- --scale_factor <SCALE_FACTOR> --min_num_process <MIN_NUM_PROCS> --max_num_process <MAX_NUM_PROCS> --supervisor_config_path <SUPERVISOR_CONFIG_PATH> --queue_url <QUEUE_URL>
+/// cargo run -- --scale_factor <SCALE_FACTOR> --min_num_process <MIN_NUM_PROCS> --max_num_process <MAX_NUM_PROCS> --program <PROGRAM> --supervisor <HOST:PORT>=<SUPERVISOR_CONFIG_PATH> --source sqs --queue_url <QUEUE_URL>
 /// ```
 ///
 /// # Example
 ///
 /// ```sh
-/// cargo run -- --scale_factor 100 --min_num_process 1 --max_num_process 10 --supervisor_config_path /etc/supervisor/conf.d/myapp.conf --queue_url https://sqs.us-west-2.amazonaws.com/XXXXXX/my-queue
+/// cargo run -- --scale_factor 100 --min_num_process 1 --max_num_process 10 --program myapp --supervisor 127.0.0.1:9001=/etc/supervisor/conf.d/myapp.conf --source sqs --queue_url https://sqs.us-west-2.amazonaws.com/XXXXXX/my-queue
+/// ```
+///
+/// To scale off Kafka consumer-group lag, or across several Supervisor nodes:
+///
+/// ```sh
+/// cargo run -- --scale_factor 100 --min_num_process 1 --max_num_process 10 --program myapp --supervisor node1:9001=/etc/supervisor/conf.d/myapp.conf --supervisor node2:9001=/etc/supervisor/conf.d/myapp.conf --source kafka --bootstrap_servers localhost:9092 --topic my-topic --group_id my-group
+/// ```
+///
+/// To scale several programs from one qscaler instance, describe them in a rules file instead:
+///
+/// ```sh
+/// cargo run -- --rules_file /etc/qscaler/rules.toml
 /// ```
 ///
 /// # Tests
 ///
 /// The program includes several tests to verify its functionality:
 ///
-/// - `test_get_cpu_usage`: Tests that the CPU usage is within a valid range.
-/// - `test_get_sqs_queue_length`: Tests that the SQS queue length is retrieved correctly.
-/// - `test_get_current_num_procs`: Tests that the current number of processes is read correctly from the configuration file.
-/// - `test_update_supervisor_config`: Tests that the Supervisor configuration file is updated correctly.
-/// - `test_reload_supervisor`: Tests that the Supervisor configuration is reloaded correctly (requires `supervisorctl` to be installed and configured).
-use aws_sdk_sqs::types::QueueAttributeName;
-use aws_sdk_sqs::{Client, Error};
-use clap::Parser;
-use std::fs::OpenOptions;
-use std::io::{self, BufRead, BufReader, Write};
-use std::process::Command;
-use sysinfo::{CpuRefreshKind, RefreshKind, System};
-use tokio::time::{sleep, Duration};
+/// - `cpu::host::tests::test_host_cpu_provider_usage`: Tests that host CPU usage is within a valid range.
+/// - `config::tests::test_get_current_num_procs`: Tests that the current number of processes is read correctly from the configuration file.
+/// - `config::tests::test_update_supervisor_config`: Tests that the Supervisor configuration file is updated correctly.
+/// - `supervisor::tests::test_supervisor_client_reload_config`: Tests that a Supervisor instance can be reloaded over XML-RPC (requires a Supervisor inet HTTP server to be running).
+use aws_sdk_cloudwatch::Client as CloudWatchClient;
+use aws_sdk_sqs::Client as SqsClient;
+use clap::{Parser, ValueEnum};
+use config::get_current_num_procs;
+use control::WorkerSupervisor;
+use controller::ScaleController;
+use cpu::{CgroupCpuProvider, CpuProvider, HostCpuProvider};
+use serde::Deserialize;
+use source::{target_num_procs, KafkaSource, ScalingSource, SqsSource};
+use supervisor::{SupervisorEndpoint, SupervisorFleet};
+use tokio::time::Duration;
+use worker::Worker;
+
+mod config;
+mod control;
+mod control_socket;
+mod controller;
+mod cpu;
+mod rules;
+mod source;
+mod supervisor;
+mod worker;
+
 const MAX_CPU_USAGE: f32 = 75.0;
 const TIME_INTERVAL: Duration = Duration::from_secs(60);
 
+/// Which backend to pull the backlog signal from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SourceKind {
+    Sqs,
+    Kafka,
+}
+
+/// Which CPU usage provider to gate scaling on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum CpuSourceKind {
+    Host,
+    Cgroup,
+}
+
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 struct Args {
-    /// Sets the maximum number of processes
-    #[arg(short = 'x', long = "max_num_process", value_name = "max_num_process")]
-    max_num_process: usize,
+    /// Sets the maximum number of processes (ignored, and not required, when `--rules_file` is given)
+    #[arg(
+        short = 'x',
+        long = "max_num_process",
+        value_name = "max_num_process",
+        required_unless_present = "rules_file"
+    )]
+    max_num_process: Option<usize>,
 
-    /// Sets the minimum number of processes
-    #[arg(short = 'm', long = "min_num_process", value_name = "min_num_process")]
-    min_num_process: usize,
+    /// Sets the minimum number of processes (ignored, and not required, when `--rules_file` is given)
+    #[arg(
+        short = 'm',
+        long = "min_num_process",
+        value_name = "min_num_process",
+        required_unless_present = "rules_file"
+    )]
+    min_num_process: Option<usize>,
 
-    /// Sets the URL of the SQS queue
-    #[arg(short = 'q', long = "queue_url", value_name = "queue_url")]
-    queue_url: String,
+    /// Sets the scale factor for the number of processes (ignored, and not required, when `--rules_file` is given)
+    #[arg(
+        short = 's',
+        long = "scale_factor",
+        value_name = "scale_factor",
+        required_unless_present = "rules_file"
+    )]
+    scale_factor: Option<usize>,
 
-    /// Sets the scale factor for the number of processes
-    #[arg(short = 's', long = "scale_factor", value_name = "scale_factor")]
-    scale_factor: usize,
+    /// Name of the Supervisor program/process group being scaled (ignored, and not required, when `--rules_file` is given)
+    #[arg(long = "program", value_name = "program", required_unless_present = "rules_file")]
+    program: Option<String>,
 
-    /// Sets the path to the Supervisor configuration file
+    /// Supervisor XML-RPC endpoint to scale, as `host:port=config_path`, where `config_path` is
+    /// that endpoint's own on-disk program config (repeat to distribute across several nodes;
+    /// ignored, and not required, when `--rules_file` is given)
     #[arg(
-        short = 'c',
-        long = "supervisor_config_path",
-        value_name = "supervisor_config_path"
+        long = "supervisor",
+        value_name = "host:port=config_path",
+        required_unless_present = "rules_file"
     )]
-    supervisor_config_path: String,
-}
+    supervisor: Vec<SupervisorEndpoint>,
+
+    /// Which backlog backend to scale against
+    #[arg(long = "source", value_enum, default_value_t = SourceKind::Sqs)]
+    source: SourceKind,
+
+    /// Sets the URL of the SQS queue (required when `--source sqs`)
+    #[arg(long = "queue_url", value_name = "queue_url")]
+    queue_url: Option<String>,
+
+    /// Kafka bootstrap servers, e.g. `localhost:9092` (required when `--source kafka`)
+    #[arg(long = "bootstrap_servers", value_name = "bootstrap_servers")]
+    bootstrap_servers: Option<String>,
+
+    /// Kafka topic to measure consumer-group lag on (required when `--source kafka`)
+    #[arg(long = "topic", value_name = "topic")]
+    topic: Option<String>,
+
+    /// Kafka consumer group id whose committed offsets are compared against the topic's high watermark (required when `--source kafka`)
+    #[arg(long = "group_id", value_name = "group_id")]
+    group_id: Option<String>,
+
+    /// Path to the Unix control socket accepting `pause`/`resume`/`cancel`/`status` commands
+    #[arg(
+        long = "control_socket",
+        value_name = "control_socket",
+        default_value = "/tmp/qscaler.sock"
+    )]
+    control_socket: String,
+
+    /// Which CPU usage provider to gate scaling on
+    #[arg(long = "cpu_source", value_enum, default_value_t = CpuSourceKind::Host)]
+    cpu_source: CpuSourceKind,
+
+    /// Minimum time to wait between consecutive scale-ups
+    #[arg(long = "scale_up_cooldown", value_name = "seconds", default_value_t = 60)]
+    scale_up_cooldown: u64,
 
-async fn get_cpu_usage() -> f32 {
-    let mut system =
-        System::new_with_specifics(RefreshKind::default().with_cpu(CpuRefreshKind::everything()));
-    sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL).await;
-    system.refresh_cpu_usage();
-    system.global_cpu_usage()
+    /// Minimum time to wait between consecutive scale-downs
+    #[arg(long = "scale_down_cooldown", value_name = "seconds", default_value_t = 300)]
+    scale_down_cooldown: u64,
+
+    /// Number of consecutive ticks a new target must hold before it is acted on
+    #[arg(long = "consecutive_ticks", value_name = "consecutive_ticks", default_value_t = 3)]
+    consecutive_ticks: u32,
+
+    /// Path to persist the last applied process count and scale timestamp across restarts
+    #[arg(
+        long = "state_path",
+        value_name = "state_path",
+        default_value = "/tmp/qscaler.state"
+    )]
+    state_path: String,
+
+    /// How long the oldest backlog item can wait before scaling counts
+    /// messages already in flight as needing workers too, instead of
+    /// sizing for visible messages alone
+    #[arg(long = "max_backlog_age", value_name = "seconds", default_value_t = 300)]
+    max_backlog_age: u64,
+
+    /// Signal sent to a process before it is removed by a scale-down, e.g. `TERM`
+    #[arg(long = "stop_signal", value_name = "signal", default_value = "TERM")]
+    stop_signal: String,
+
+    /// How long to wait for a signaled process to exit on its own before force-stopping it
+    #[arg(long = "stop_timeout", value_name = "seconds", default_value_t = 10)]
+    stop_timeout: u64,
+
+    /// Path to a TOML rules file describing several program sections to scale together,
+    /// replacing the single program named by the other flags
+    #[arg(long = "rules_file", value_name = "rules_file")]
+    rules_file: Option<String>,
 }
 
-async fn get_sqs_queue_length(client: &Client, queue_url: &str) -> Result<usize, Error> {
-    let response = client
-        .get_queue_attributes()
-        .queue_url(queue_url)
-        .attribute_names(QueueAttributeName::ApproximateNumberOfMessages)
-        .send()
-        .await?;
-
-    if let Some(attributes) = response.attributes {
-        if let Some(message_count) =
-            attributes.get(&QueueAttributeName::ApproximateNumberOfMessages)
-        {
-            return Ok(message_count.parse::<usize>().unwrap_or(0));
-        }
-    }
-    Ok(0)
+/// Which backend to pull backlog from for one program, and that backend's own flags.
+/// Carried separately from `Args`/`ProgramRule` so `build_source` works for either.
+pub(crate) struct SourceSpec {
+    pub kind: SourceKind,
+    pub queue_url: Option<String>,
+    pub bootstrap_servers: Option<String>,
+    pub topic: Option<String>,
+    pub group_id: Option<String>,
 }
 
-async fn get_current_num_procs(path: &str) -> io::Result<usize> {
-    let file = OpenOptions::new().read(true).open(path)?;
-    let reader = BufReader::new(file);
-
-    for line in reader.lines() {
-        let line = line?;
-        if line.starts_with("numprocs=") {
-            let parts: Vec<&str> = line.split('=').collect();
-            if parts.len() == 2 {
-                if let Ok(num_procs) = parts[1].trim().parse::<usize>() {
-                    return Ok(num_procs);
-                }
-            }
-        }
+/// Builds the configured `CpuProvider`.
+fn build_cpu_provider(kind: CpuSourceKind) -> Box<dyn CpuProvider> {
+    match kind {
+        CpuSourceKind::Host => Box::new(HostCpuProvider),
+        CpuSourceKind::Cgroup => Box::new(CgroupCpuProvider::new()),
     }
-    Err(io::Error::new(
-        io::ErrorKind::NotFound,
-        "numprocs not found",
-    ))
 }
 
-async fn update_supervisor_config(num_procs: usize, path: &str) -> io::Result<()> {
-    let file = OpenOptions::new().read(true).open(path)?;
-    let reader = BufReader::new(file);
-    let mut lines: Vec<String> = Vec::new();
-
-    for line in reader.lines() {
-        let mut line = line?;
-        if line.starts_with("numprocs=") {
-            line = format!("numprocs={}", num_procs);
+/// Builds the configured `ScalingSource`.
+async fn build_source(spec: &SourceSpec) -> Result<Box<dyn ScalingSource>, Box<dyn std::error::Error>> {
+    match spec.kind {
+        SourceKind::Sqs => {
+            let queue_url = spec
+                .queue_url
+                .clone()
+                .ok_or("--queue_url is required when --source sqs")?;
+            let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+            let sqs = SqsClient::new(&config);
+            let cloudwatch = CloudWatchClient::new(&config);
+            Ok(Box::new(SqsSource::new(sqs, cloudwatch, queue_url)))
+        }
+        SourceKind::Kafka => {
+            let bootstrap_servers = spec
+                .bootstrap_servers
+                .clone()
+                .ok_or("--bootstrap_servers is required when --source kafka")?;
+            let topic = spec
+                .topic
+                .clone()
+                .ok_or("--topic is required when --source kafka")?;
+            let group_id = spec
+                .group_id
+                .clone()
+                .ok_or("--group_id is required when --source kafka")?;
+            Ok(Box::new(KafkaSource::new(&bootstrap_servers, &group_id, topic)?))
         }
-        lines.push(line);
-    }
-
-    let mut file = OpenOptions::new().write(true).truncate(true).open(path)?;
-    for line in lines {
-        writeln!(file, "{}", line)?;
     }
-    Ok(())
 }
 
-async fn reload_supervisor() -> io::Result<()> {
-    let output = Command::new("sudo")
-        .arg("supervisorctl")
-        .arg("reread")
-        .output()
-        .expect("Failed to execute supervisorctl reread command");
-
-    if !output.status.success() {
-        eprintln!(
-            "supervisorctl reread failed: {}",
-            String::from_utf8_lossy(&output.stdout)
-        );
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            "Failed to reread supervisor config",
-        ));
-    }
-
-    let output = Command::new("sudo")
-        .arg("supervisorctl")
-        .arg("update")
-        .output()
-        .expect("Failed to execute supervisorctl update command");
+/// Everything needed to scale one Supervisor program, gathered from either
+/// the single-program CLI flags or one entry of a `--rules_file`.
+pub(crate) struct ProgramSpec {
+    pub program: String,
+    pub supervisor: Vec<SupervisorEndpoint>,
+    pub scale_factor: usize,
+    pub min_num_process: usize,
+    pub max_num_process: usize,
+    pub source: SourceSpec,
+    pub cpu_source: CpuSourceKind,
+    pub scale_up_cooldown: u64,
+    pub scale_down_cooldown: u64,
+    pub consecutive_ticks: u32,
+    pub state_path: String,
+    pub max_backlog_age: u64,
+    pub stop_signal: String,
+    pub stop_timeout: u64,
+    pub control_socket: String,
+}
 
-    if !output.status.success() {
-        eprintln!(
-            "supervisorctl update failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        );
-        return Err(io::Error::new(
-            io::ErrorKind::Other,
-            "Failed to update supervisor config",
-        ));
-    }
+/// Builds a `ScalingWorker` and its control socket path from a `ProgramSpec`.
+async fn build_scaling_worker(
+    spec: ProgramSpec,
+) -> Result<(ScalingWorker, String), Box<dyn std::error::Error>> {
+    let source = build_source(&spec.source).await?;
+    let fleet = SupervisorFleet::new(&spec.supervisor);
+    let cpu_provider = build_cpu_provider(spec.cpu_source);
+    // Any endpoint's config reflects the program's current numprocs at
+    // startup; the first one is as good a guess as any, and this is only
+    // ever a seed for the controller's first decision.
+    let initial_target = match spec.supervisor.first() {
+        Some(endpoint) => get_current_num_procs(&endpoint.config_path)
+            .await
+            .unwrap_or(spec.min_num_process),
+        None => spec.min_num_process,
+    };
+    let controller = ScaleController::new(
+        Duration::from_secs(spec.scale_up_cooldown),
+        Duration::from_secs(spec.scale_down_cooldown),
+        spec.consecutive_ticks,
+        spec.state_path,
+        initial_target,
+    );
 
-    Ok(())
+    let worker = ScalingWorker {
+        scale_factor: spec.scale_factor,
+        min_num_process: spec.min_num_process,
+        max_num_process: spec.max_num_process,
+        program: spec.program,
+        max_backlog_age: Duration::from_secs(spec.max_backlog_age),
+        stop_signal: spec.stop_signal,
+        stop_timeout: Duration::from_secs(spec.stop_timeout),
+        fleet,
+        source,
+        cpu_provider,
+        controller,
+    };
+    Ok((worker, spec.control_socket))
 }
 
-async fn scaling_loop(
+/// One scaling decision, wrapped as a `Worker` so `WorkerSupervisor` can
+/// drive it a tick at a time and answer control commands in between.
+struct ScalingWorker {
     scale_factor: usize,
     min_num_process: usize,
     max_num_process: usize,
-    supervisor_config_path: &str,
-    queue_url: &str,
-) {
-    let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
-    let client = Client::new(&config);
-    let mut interval = tokio::time::interval(TIME_INTERVAL);
-    loop {
-        interval.tick().await;
-
-        let cpu_usage = get_cpu_usage().await;
-        // If CPU usage is high, continue
+    program: String,
+    max_backlog_age: Duration,
+    stop_signal: String,
+    stop_timeout: Duration,
+    fleet: SupervisorFleet,
+    source: Box<dyn ScalingSource>,
+    cpu_provider: Box<dyn CpuProvider>,
+    controller: ScaleController,
+}
+
+#[async_trait::async_trait]
+impl Worker for ScalingWorker {
+    async fn tick(&mut self) -> Result<Option<usize>, Box<dyn std::error::Error + Send + Sync>> {
+        let cpu_usage = self.cpu_provider.usage().await;
+        // If CPU usage is high, skip this tick
         if cpu_usage >= MAX_CPU_USAGE {
             println!(
                 "CPU usage is high ({}%), waiting for 60 seconds...",
                 cpu_usage
             );
-            continue;
+            return Ok(None);
         }
 
-        // Calculate the number of processes based on the queue length
-        let queue_length = get_sqs_queue_length(&client, queue_url).await.unwrap_or(0);
-        let num_procs: usize =
-            (queue_length / scale_factor).clamp(min_num_process, max_num_process);
-
-        let current_num_procs = get_current_num_procs(supervisor_config_path).await.unwrap();
-        // If the number of processes is already at the desired level, continue
-        if num_procs == current_num_procs {
-            continue;
-        }
+        // Calculate the raw target based on the backlog, then debounce it
+        let backlog = self.source.backlog().await.unwrap_or_default();
+        let computed_target = target_num_procs(
+            &backlog,
+            self.scale_factor,
+            self.min_num_process,
+            self.max_num_process,
+            self.max_backlog_age,
+        );
 
-        // Update the Supervisor configuration and reload the Supervisor process
-        if update_supervisor_config(num_procs, supervisor_config_path)
+        let previous_target = self.controller.current_target();
+        let num_procs = match self.controller.decide(computed_target) {
+            Some(num_procs) => num_procs,
+            None => return Ok(None),
+        };
+
+        // Distribute the new process count across the healthy Supervisor endpoints and reload them
+        match self
+            .fleet
+            .apply_numprocs(
+                &self.program,
+                previous_target,
+                num_procs,
+                &self.stop_signal,
+                self.stop_timeout,
+            )
             .await
-            .is_ok()
-            && reload_supervisor().await.is_ok()
         {
-            println!(
-                "Qscaler scaling finished, CPU threshold {}%, current CPU usage: {}%, \
-                        queue length threshold {}, current queue length: {}, \
+            Ok(()) => {
+                println!(
+                    "Qscaler scaling finished, CPU threshold {}%, current CPU usage: {}%, \
+                        scale factor {}, visible messages: {}, in-flight messages: {}, \
+                        oldest message age: {:?}, \
                         current number of processes {}, new number of processes {}",
-                MAX_CPU_USAGE, cpu_usage, scale_factor, queue_length, current_num_procs, num_procs
-            );
-        } else {
-            eprintln!(
-                "Fail to scaling processes, rollback the number of processes {}",
-                current_num_procs
-            );
-            update_supervisor_config(current_num_procs, supervisor_config_path)
-                .await
-                .expect("Fail to rollback the number of processes");
-            reload_supervisor()
-                .await
-                .expect("Fail to reload supervisor");
+                    MAX_CPU_USAGE,
+                    cpu_usage,
+                    self.scale_factor,
+                    backlog.visible,
+                    backlog.in_flight,
+                    backlog.oldest_age,
+                    previous_target,
+                    num_procs
+                );
+                Ok(Some(num_procs))
+            }
+            Err(err) => {
+                eprintln!(
+                    "Fail to scale processes ({}), rollback the number of processes {}",
+                    err, previous_target
+                );
+                match self
+                    .fleet
+                    .apply_numprocs(
+                        &self.program,
+                        num_procs,
+                        previous_target,
+                        &self.stop_signal,
+                        self.stop_timeout,
+                    )
+                    .await
+                {
+                    Ok(()) => {
+                        self.controller.revert(previous_target);
+                        Err(err)
+                    }
+                    // Surface both failures through last_error rather than
+                    // panicking the worker task: a rollback run right after
+                    // a failed scale is likely hitting the same unreachable
+                    // endpoint, and WorkerSupervisor exists so an operator
+                    // can observe that without the process going down.
+                    Err(rollback_err) => Err(format!(
+                        "scale failed ({err}), then rollback also failed ({rollback_err})"
+                    )
+                    .into()),
+                }
+            }
         }
     }
 }
 
-#[tokio::main]
-async fn main() {
-    let args = Args::parse();
-    println!(
-        "Qscaler started with min_num_process: {}, max_num_process: {}, supervisor_config_path: {}, queue_url: {}",
-        args.min_num_process, args.max_num_process, args.supervisor_config_path, args.queue_url
-    );
+/// Runs one `ScalingWorker` to completion under its own `WorkerSupervisor`
+/// and control socket. Used once for the single-program CLI invocation, and
+/// once per program section when running off a `--rules_file`.
+async fn run_program(spec: ProgramSpec) {
+    let program = spec.program.clone();
+    let (worker, control_socket_path) = build_scaling_worker(spec)
+        .await
+        .unwrap_or_else(|err| panic!("Failed to initialize scaling worker for {}: {}", program, err));
+    let (supervisor, commands) = WorkerSupervisor::new(worker, TIME_INTERVAL);
+
+    tokio::spawn(async move {
+        if let Err(err) = control_socket::serve(&control_socket_path, commands).await {
+            eprintln!("Control socket on {} failed: {}", control_socket_path, err);
+        }
+    });
 
-    scaling_loop(
-        args.scale_factor,
-        args.min_num_process,
-        args.max_num_process,
-        &args.supervisor_config_path,
-        &args.queue_url,
-    )
-    .await;
+    supervisor.run().await;
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs::File;
-    use std::io::Write;
-    use tokio::fs;
-
-    #[tokio::test]
-    async fn test_get_cpu_usage() {
-        let cpu_usage = get_cpu_usage().await;
-        assert!(cpu_usage >= 0.0 && cpu_usage <= 100.0);
-    }
-
-    #[tokio::test]
-    async fn test_get_sqs_queue_length() {
-        let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
-        let client = Client::new(&config);
-        let queue_url =
-            "https://sqs.us-west-2.amazonaws.com/XXXXXX/test.fifo";
-        let queue_length = get_sqs_queue_length(&client, queue_url).await.unwrap();
-        assert!(queue_length >= 0);
+impl Args {
+    /// Builds the single `ProgramSpec` described by the legacy, single-program flags.
+    fn into_program_spec(self) -> ProgramSpec {
+        ProgramSpec {
+            program: self.program.expect("--program is required without --rules_file"),
+            supervisor: self.supervisor,
+            scale_factor: self.scale_factor.expect("--scale_factor is required without --rules_file"),
+            min_num_process: self
+                .min_num_process
+                .expect("--min_num_process is required without --rules_file"),
+            max_num_process: self
+                .max_num_process
+                .expect("--max_num_process is required without --rules_file"),
+            source: SourceSpec {
+                kind: self.source,
+                queue_url: self.queue_url,
+                bootstrap_servers: self.bootstrap_servers,
+                topic: self.topic,
+                group_id: self.group_id,
+            },
+            cpu_source: self.cpu_source,
+            scale_up_cooldown: self.scale_up_cooldown,
+            scale_down_cooldown: self.scale_down_cooldown,
+            consecutive_ticks: self.consecutive_ticks,
+            state_path: self.state_path,
+            max_backlog_age: self.max_backlog_age,
+            stop_signal: self.stop_signal,
+            stop_timeout: self.stop_timeout,
+            control_socket: self.control_socket,
+        }
     }
+}
 
-    #[tokio::test]
-    async fn test_get_current_num_procs() {
-        let test_config_path = "example/sample.conf";
-        let mut file = File::create(test_config_path).unwrap();
-        writeln!(file, "numprocs=5").unwrap();
-
-        let num_procs = get_current_num_procs(test_config_path).await.unwrap();
-        assert_eq!(num_procs, 5);
-
-        fs::remove_file(test_config_path).await.unwrap();
-    }
+#[tokio::main]
+async fn main() {
+    let args = Args::parse();
 
-    #[tokio::test]
-    async fn test_update_supervisor_config() {
-        let test_config_path = "test_supervisor.conf";
-        let mut file = File::create(test_config_path).unwrap();
-        writeln!(file, "numprocs=5").unwrap();
+    let Some(rules_file) = args.rules_file.clone() else {
+        println!(
+            "Qscaler started with min_num_process: {:?}, max_num_process: {:?}, program: {:?}, supervisor endpoints: {:?}, source: {:?}",
+            args.min_num_process, args.max_num_process, args.program, args.supervisor.iter().map(|e| e.addr.as_str()).collect::<Vec<_>>(), args.source
+        );
+        run_program(args.into_program_spec()).await;
+        return;
+    };
 
-        update_supervisor_config(10, test_config_path)
-            .await
-            .unwrap();
+    let rules = rules::load_rules_file(&rules_file).expect("Failed to read --rules_file");
+    println!(
+        "Qscaler started from rules file {} with {} program(s)",
+        rules_file,
+        rules.programs.len()
+    );
 
-        let num_procs = get_current_num_procs(test_config_path).await.unwrap();
-        assert_eq!(num_procs, 10);
+    let default_control_socket = args.control_socket;
+    let mut handles = Vec::with_capacity(rules.programs.len());
+    for rule in rules.programs {
+        let spec = rule.into_program_spec(&default_control_socket);
+        handles.push(tokio::spawn(run_program(spec)));
     }
-
-    #[tokio::test]
-    async fn test_reload_supervisor() {
-        // This test assumes that supervisorctl is installed and configured correctly.
-        // It will not work in an environment where supervisorctl is not available.
-        let result = reload_supervisor().await;
-        assert!(result.is_ok());
+    for handle in handles {
+        let _ = handle.await;
     }
 }
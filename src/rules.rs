@@ -0,0 +1,179 @@
+use crate::supervisor::SupervisorEndpoint;
+use crate::{CpuSourceKind, ProgramSpec, SourceKind, SourceSpec};
+use serde::Deserialize;
+use std::fs;
+use std::io;
+
+fn default_source() -> SourceKind {
+    SourceKind::Sqs
+}
+
+fn default_cpu_source() -> CpuSourceKind {
+    CpuSourceKind::Host
+}
+
+fn default_scale_up_cooldown() -> u64 {
+    60
+}
+
+fn default_scale_down_cooldown() -> u64 {
+    300
+}
+
+fn default_consecutive_ticks() -> u32 {
+    3
+}
+
+fn default_max_backlog_age() -> u64 {
+    300
+}
+
+fn default_stop_signal() -> String {
+    "TERM".to_string()
+}
+
+fn default_stop_timeout() -> u64 {
+    10
+}
+
+/// One program section of a `--rules_file`: everything `ProgramSpec` needs
+/// to run a `ScalingWorker` for a single Supervisor program. Fields mirror
+/// the single-program CLI flags one-for-one, with the same defaults, so a
+/// rules file reads like several flattened invocations of qscaler.
+#[derive(Clone, Deserialize)]
+pub struct ProgramRule {
+    pub program: String,
+    pub supervisor: Vec<SupervisorEndpoint>,
+    pub scale_factor: usize,
+    pub min_num_process: usize,
+    pub max_num_process: usize,
+
+    #[serde(default = "default_source")]
+    pub source: SourceKind,
+    pub queue_url: Option<String>,
+    pub bootstrap_servers: Option<String>,
+    pub topic: Option<String>,
+    pub group_id: Option<String>,
+
+    #[serde(default = "default_cpu_source")]
+    pub cpu_source: CpuSourceKind,
+    #[serde(default = "default_scale_up_cooldown")]
+    pub scale_up_cooldown: u64,
+    #[serde(default = "default_scale_down_cooldown")]
+    pub scale_down_cooldown: u64,
+    #[serde(default = "default_consecutive_ticks")]
+    pub consecutive_ticks: u32,
+    #[serde(default = "default_max_backlog_age")]
+    pub max_backlog_age: u64,
+    #[serde(default = "default_stop_signal")]
+    pub stop_signal: String,
+    #[serde(default = "default_stop_timeout")]
+    pub stop_timeout: u64,
+
+    /// Defaults to `/tmp/qscaler-<program>.state` if unset, so sibling
+    /// programs in the same rules file don't clobber each other's state.
+    pub state_path: Option<String>,
+    /// Defaults to `<control_socket>-<program>` if unset.
+    pub control_socket: Option<String>,
+}
+
+impl ProgramRule {
+    /// Converts this rule into a `ProgramSpec`, filling in per-program
+    /// defaults for `state_path`/`control_socket` derived from the
+    /// program name when the rule doesn't set them explicitly.
+    pub fn into_program_spec(self, default_control_socket: &str) -> ProgramSpec {
+        let state_path = self
+            .state_path
+            .unwrap_or_else(|| format!("/tmp/qscaler-{}.state", self.program));
+        let control_socket = self
+            .control_socket
+            .unwrap_or_else(|| format!("{}-{}", default_control_socket, self.program));
+
+        ProgramSpec {
+            program: self.program,
+            supervisor: self.supervisor,
+            scale_factor: self.scale_factor,
+            min_num_process: self.min_num_process,
+            max_num_process: self.max_num_process,
+            source: SourceSpec {
+                kind: self.source,
+                queue_url: self.queue_url,
+                bootstrap_servers: self.bootstrap_servers,
+                topic: self.topic,
+                group_id: self.group_id,
+            },
+            cpu_source: self.cpu_source,
+            scale_up_cooldown: self.scale_up_cooldown,
+            scale_down_cooldown: self.scale_down_cooldown,
+            consecutive_ticks: self.consecutive_ticks,
+            state_path,
+            max_backlog_age: self.max_backlog_age,
+            stop_signal: self.stop_signal,
+            stop_timeout: self.stop_timeout,
+            control_socket,
+        }
+    }
+}
+
+/// The top-level shape of a `--rules_file`: a list of program sections to
+/// scale side by side from one qscaler instance.
+#[derive(Deserialize)]
+pub struct RulesFile {
+    pub programs: Vec<ProgramRule>,
+}
+
+/// Parses a TOML rules file into a `RulesFile`.
+pub fn load_rules_file(path: &str) -> io::Result<RulesFile> {
+    let contents = fs::read_to_string(path)?;
+    toml::from_str(&contents).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_rules_file_parses_multiple_programs() {
+        let path = "/tmp/qscaler_rules_test.toml";
+        fs::write(
+            path,
+            r#"
+            [[programs]]
+            program = "ingest"
+            scale_factor = 50
+            min_num_process = 1
+            max_num_process = 5
+            source = "sqs"
+            queue_url = "https://sqs.us-west-2.amazonaws.com/XXXXXX/ingest"
+
+            [[programs.supervisor]]
+            addr = "127.0.0.1:9001"
+            config_path = "/etc/supervisor/conf.d/ingest.conf"
+
+            [[programs]]
+            program = "export"
+            scale_factor = 100
+            min_num_process = 1
+            max_num_process = 10
+            source = "kafka"
+            bootstrap_servers = "localhost:9092"
+            topic = "export-jobs"
+            group_id = "export-workers"
+
+            [[programs.supervisor]]
+            addr = "127.0.0.1:9001"
+            config_path = "/etc/supervisor/conf.d/export.conf"
+            "#,
+        )
+        .unwrap();
+
+        let rules = load_rules_file(path).unwrap();
+        assert_eq!(rules.programs.len(), 2);
+        assert_eq!(rules.programs[0].program, "ingest");
+        assert_eq!(rules.programs[0].cpu_source, CpuSourceKind::Host);
+        assert_eq!(rules.programs[1].program, "export");
+        assert_eq!(rules.programs[1].scale_up_cooldown, 60);
+
+        fs::remove_file(path).unwrap();
+    }
+}
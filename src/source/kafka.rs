@@ -0,0 +1,89 @@
+use super::{Backlog, ScalingSource};
+use async_trait::async_trait;
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{BaseConsumer, Consumer};
+use rdkafka::topic_partition_list::Offset;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+
+const WATERMARK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Reports backlog as the summed consumer-group lag across all partitions
+/// of a Kafka topic: for each partition, the high watermark minus the
+/// group's committed offset.
+pub struct KafkaSource {
+    consumer: Arc<BaseConsumer>,
+    topic: String,
+}
+
+impl KafkaSource {
+    pub fn new(
+        bootstrap_servers: &str,
+        group_id: &str,
+        topic: String,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let consumer: BaseConsumer = ClientConfig::new()
+            .set("bootstrap.servers", bootstrap_servers)
+            .set("group.id", group_id)
+            .set("enable.auto.commit", "false")
+            .create()?;
+        Ok(Self {
+            consumer: Arc::new(consumer),
+            topic,
+        })
+    }
+}
+
+#[async_trait]
+impl ScalingSource for KafkaSource {
+    async fn backlog(&self) -> Result<Backlog, Box<dyn Error + Send + Sync>> {
+        let consumer = Arc::clone(&self.consumer);
+        let topic = self.topic.clone();
+
+        // fetch_metadata/committed_offsets/fetch_watermarks are blocking
+        // rdkafka FFI calls that can each take up to WATERMARK_TIMEOUT, once
+        // per partition; run them on a blocking thread so they don't stall
+        // a runtime worker the tick loop and control socket also depend on.
+        tokio::task::spawn_blocking(move || -> Result<Backlog, Box<dyn Error + Send + Sync>> {
+            let metadata = consumer.fetch_metadata(Some(&topic), WATERMARK_TIMEOUT)?;
+            let topic_metadata = metadata
+                .topics()
+                .iter()
+                .find(|t| t.name() == topic)
+                .ok_or_else(|| format!("unknown Kafka topic: {}", topic))?;
+
+            let mut assignment = rdkafka::TopicPartitionList::new();
+            for partition in topic_metadata.partitions() {
+                assignment.add_partition_offset(&topic, partition.id(), Offset::Invalid)?;
+            }
+            let committed = consumer.committed_offsets(assignment, WATERMARK_TIMEOUT)?;
+
+            let mut lag: usize = 0;
+            for partition in topic_metadata.partitions() {
+                let (_low, high) =
+                    consumer.fetch_watermarks(&topic, partition.id(), WATERMARK_TIMEOUT)?;
+
+                let committed_offset = committed
+                    .find_partition(&topic, partition.id())
+                    .and_then(|p| match p.offset() {
+                        Offset::Offset(offset) => Some(offset),
+                        _ => None,
+                    })
+                    .unwrap_or(0);
+
+                lag += (high - committed_offset).max(0) as usize;
+            }
+
+            // Kafka reports lag as a single consumer-group-wide number with
+            // no notion of "in flight" or "oldest message age" the way SQS
+            // does.
+            Ok(Backlog {
+                visible: lag,
+                in_flight: 0,
+                oldest_age: None,
+            })
+        })
+        .await?
+    }
+}
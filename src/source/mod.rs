@@ -0,0 +1,118 @@
+mod kafka;
+mod sqs;
+
+pub use kafka::KafkaSource;
+pub use sqs::SqsSource;
+
+use async_trait::async_trait;
+use std::error::Error;
+use std::time::Duration;
+
+/// The backlog a source reports: not just a visible count, but enough
+/// context for the scaling decision to account for work already claimed
+/// by a worker and for how long the oldest item has been waiting.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Backlog {
+    /// Items visible and waiting to be picked up.
+    pub visible: usize,
+    /// Items already claimed by a worker and not yet acknowledged.
+    pub in_flight: usize,
+    /// Age of the oldest visible item, if the backend can report it.
+    pub oldest_age: Option<Duration>,
+}
+
+/// A backend capable of reporting how much work is waiting to be processed.
+///
+/// Implementations translate whatever native notion of "backlog" their
+/// underlying system exposes (visible and in-flight SQS messages, Kafka
+/// consumer-group lag, ...) into a `Backlog` so `ScalingWorker` can stay
+/// agnostic to whichever queue system the user runs.
+#[async_trait]
+pub trait ScalingSource: Send + Sync {
+    /// Returns the current backlog for this source.
+    async fn backlog(&self) -> Result<Backlog, Box<dyn Error + Send + Sync>>;
+}
+
+/// Derives the target process count from a `Backlog`. `visible` and
+/// `in_flight` are disjoint counts, so `visible` alone is already the
+/// right base signal; `in_flight` only comes into play when messages are
+/// aging past `max_age`, in which case workers may be stuck rather than
+/// genuinely draining them, and the target is sized for the full backlog
+/// including what's in flight.
+pub fn target_num_procs(
+    backlog: &Backlog,
+    scale_factor: usize,
+    min_num_process: usize,
+    max_num_process: usize,
+    max_age: Duration,
+) -> usize {
+    let is_aging = backlog.oldest_age.is_some_and(|age| age >= max_age);
+
+    let effective_backlog = if is_aging {
+        backlog.visible + backlog.in_flight
+    } else {
+        backlog.visible
+    };
+
+    (effective_backlog / scale_factor).clamp(min_num_process, max_num_process)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uses_visible_count_without_discounting_in_flight_when_not_aging() {
+        let backlog = Backlog {
+            visible: 100,
+            in_flight: 80,
+            oldest_age: None,
+        };
+        assert_eq!(
+            target_num_procs(&backlog, 10, 1, 10, Duration::from_secs(300)),
+            10
+        );
+    }
+
+    #[test]
+    fn test_does_not_collapse_to_zero_when_in_flight_exceeds_visible() {
+        // visible and in_flight are disjoint SQS counts, so in_flight
+        // outnumbering visible is normal under load and must not zero out
+        // a real backlog.
+        let backlog = Backlog {
+            visible: 1000,
+            in_flight: 1200,
+            oldest_age: None,
+        };
+        assert_eq!(
+            target_num_procs(&backlog, 100, 1, 20, Duration::from_secs(300)),
+            10
+        );
+    }
+
+    #[test]
+    fn test_scales_up_on_aging_backlog_by_counting_in_flight_too() {
+        let backlog = Backlog {
+            visible: 100,
+            in_flight: 80,
+            oldest_age: Some(Duration::from_secs(600)),
+        };
+        assert_eq!(
+            target_num_procs(&backlog, 10, 1, 20, Duration::from_secs(300)),
+            18
+        );
+    }
+
+    #[test]
+    fn test_clamps_to_configured_bounds() {
+        let backlog = Backlog {
+            visible: 5,
+            in_flight: 0,
+            oldest_age: None,
+        };
+        assert_eq!(
+            target_num_procs(&backlog, 10, 3, 10, Duration::from_secs(300)),
+            3
+        );
+    }
+}
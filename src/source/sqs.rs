@@ -0,0 +1,121 @@
+use super::{Backlog, ScalingSource};
+use async_trait::async_trait;
+use aws_sdk_cloudwatch::types::{Dimension, Statistic};
+use aws_sdk_cloudwatch::Client as CloudWatchClient;
+use aws_sdk_sqs::types::QueueAttributeName;
+use aws_sdk_sqs::Client as SqsClient;
+use aws_smithy_types::DateTime as AwsDateTime;
+use std::error::Error;
+use std::time::{Duration, SystemTime};
+
+/// How far back to look for an `ApproximateAgeOfOldestMessage` datapoint.
+/// CloudWatch publishes this SQS metric roughly once a minute; a wider
+/// lookback window tolerates a slow or missing datapoint without treating
+/// the queue as having no age data at all.
+const METRIC_LOOKBACK: Duration = Duration::from_secs(600);
+const METRIC_PERIOD_SECS: i32 = 60;
+
+/// Reports backlog from an AWS SQS queue: `ApproximateNumberOfMessages` for
+/// visible messages, `ApproximateNumberOfMessagesNotVisible` for in-flight
+/// ones, and the age of the oldest visible message from CloudWatch's
+/// `ApproximateAgeOfOldestMessage` metric. The metric is used instead of a
+/// `receive_message` peek because SQS bumps a message's
+/// `ApproximateReceiveCount` on every receive regardless of the visibility
+/// timeout passed, so peeking the same aging message once a tick would
+/// eventually drive it into a redrive policy's DLQ on its own.
+pub struct SqsSource {
+    sqs: SqsClient,
+    cloudwatch: CloudWatchClient,
+    queue_url: String,
+    queue_name: String,
+}
+
+impl SqsSource {
+    pub fn new(sqs: SqsClient, cloudwatch: CloudWatchClient, queue_url: String) -> Self {
+        let queue_name = queue_url.rsplit('/').next().unwrap_or(&queue_url).to_string();
+        Self {
+            sqs,
+            cloudwatch,
+            queue_url,
+            queue_name,
+        }
+    }
+
+    async fn oldest_age(&self) -> Option<Duration> {
+        let now = SystemTime::now();
+        let start_time = now.checked_sub(METRIC_LOOKBACK).unwrap_or(now);
+
+        let response = self
+            .cloudwatch
+            .get_metric_statistics()
+            .namespace("AWS/SQS")
+            .metric_name("ApproximateAgeOfOldestMessage")
+            .dimensions(Dimension::builder().name("QueueName").value(&self.queue_name).build())
+            .start_time(AwsDateTime::from(start_time))
+            .end_time(AwsDateTime::from(now))
+            .period(METRIC_PERIOD_SECS)
+            .statistics(Statistic::Maximum)
+            .send()
+            .await
+            .ok()?;
+
+        let latest = response
+            .datapoints
+            .unwrap_or_default()
+            .into_iter()
+            .max_by_key(|datapoint| datapoint.timestamp.map(|t| t.secs()).unwrap_or(i64::MIN))?;
+
+        Some(Duration::from_secs_f64(latest.maximum?.max(0.0)))
+    }
+}
+
+#[async_trait]
+impl ScalingSource for SqsSource {
+    async fn backlog(&self) -> Result<Backlog, Box<dyn Error + Send + Sync>> {
+        let response = self
+            .sqs
+            .get_queue_attributes()
+            .queue_url(&self.queue_url)
+            .attribute_names(QueueAttributeName::ApproximateNumberOfMessages)
+            .attribute_names(QueueAttributeName::ApproximateNumberOfMessagesNotVisible)
+            .send()
+            .await?;
+
+        let attributes = response.attributes.unwrap_or_default();
+        let visible = attributes
+            .get(&QueueAttributeName::ApproximateNumberOfMessages)
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(0);
+        let in_flight = attributes
+            .get(&QueueAttributeName::ApproximateNumberOfMessagesNotVisible)
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(0);
+
+        Ok(Backlog {
+            visible,
+            in_flight,
+            oldest_age: self.oldest_age().await,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_config::BehaviorVersion;
+
+    #[tokio::test]
+    async fn test_sqs_source_backlog() {
+        let config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+        let sqs = SqsClient::new(&config);
+        let cloudwatch = CloudWatchClient::new(&config);
+        let source = SqsSource::new(
+            sqs,
+            cloudwatch,
+            "https://sqs.us-west-2.amazonaws.com/XXXXXX/test.fifo".to_string(),
+        );
+        let backlog = source.backlog().await.unwrap();
+        assert!(backlog.visible < usize::MAX);
+        assert!(backlog.in_flight < usize::MAX);
+    }
+}
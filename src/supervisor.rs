@@ -0,0 +1,350 @@
+use crate::config::{get_current_num_procs, update_supervisor_config};
+use serde::Deserialize;
+use std::error::Error;
+use std::str::FromStr;
+use tokio::time::{sleep, Duration, Instant};
+use xmlrpc::{Request, Value};
+
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// One Supervisor endpoint to scale, paired with the path to *that
+/// endpoint's* own on-disk program config. A fleet's endpoints can live on
+/// different hosts, so each one needs its own config path to rewrite —
+/// there's no single shared file a remote node's `numprocs=` lives in.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SupervisorEndpoint {
+    pub addr: String,
+    pub config_path: String,
+}
+
+impl FromStr for SupervisorEndpoint {
+    type Err = String;
+
+    /// Parses a CLI value of the form `host:port=config_path`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, config_path) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected `host:port=config_path`, got `{s}`"))?;
+        Ok(Self {
+            addr: addr.to_string(),
+            config_path: config_path.to_string(),
+        })
+    }
+}
+
+/// A single Supervisor instance reachable over its XML-RPC HTTP endpoint
+/// (the same interface `supervisorctl` talks to), addressed as
+/// `host:port`. Talking XML-RPC directly means qscaler no longer needs
+/// `sudo` or a local shell on the machine Supervisor runs on.
+pub struct SupervisorClient {
+    endpoint: String,
+    config_path: String,
+}
+
+impl SupervisorClient {
+    pub fn new(endpoint: &SupervisorEndpoint) -> Self {
+        Self {
+            endpoint: endpoint.addr.clone(),
+            config_path: endpoint.config_path.clone(),
+        }
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// The local path to this endpoint's own Supervisor program config.
+    pub fn config_path(&self) -> &str {
+        &self.config_path
+    }
+
+    fn url(&self) -> String {
+        format!("http://{}/RPC2", self.endpoint)
+    }
+
+    async fn call(&self, method: &str, params: Vec<Value>) -> Result<Value, Box<dyn Error + Send + Sync>> {
+        let url = self.url();
+        let method = method.to_string();
+        tokio::task::spawn_blocking(move || -> Result<Value, Box<dyn Error + Send + Sync>> {
+            let mut request = Request::new(&method);
+            for param in params {
+                request = request.arg(param);
+            }
+            Ok(request.call_url(&url)?)
+        })
+        .await?
+    }
+
+    /// Re-reads supervisord's configuration files from disk, picking up
+    /// whatever `numprocs=` change was just written.
+    pub async fn reload_config(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.call("supervisor.reloadConfig", vec![]).await?;
+        Ok(())
+    }
+
+    /// Starts a process group that a prior `reload_config` reported as
+    /// added, growing the program's process count. Faults with
+    /// `ALREADY_ADDED` if the group is already running; call
+    /// `stop_process_group`/`remove_process_group` first to re-add one.
+    pub async fn add_process_group(&self, name: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.call(
+            "supervisor.addProcessGroup",
+            vec![Value::String(name.to_string())],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Stops every process in a group, required before it can be removed.
+    pub async fn stop_process_group(&self, name: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.call(
+            "supervisor.stopProcessGroup",
+            vec![Value::String(name.to_string())],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Removes a stopped process group from Supervisor's process table so
+    /// `add_process_group` can register it again with a changed `numprocs`.
+    pub async fn remove_process_group(&self, name: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.call(
+            "supervisor.removeProcessGroup",
+            vec![Value::String(name.to_string())],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Stops a single process, used to shrink a program's process count
+    /// ahead of lowering `numprocs`.
+    pub async fn stop_process(&self, name: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.call(
+            "supervisor.stopProcess",
+            vec![Value::String(name.to_string()), Value::Bool(true)],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Sends `signal` (e.g. `"TERM"`) to a running process without
+    /// stopping it through Supervisor's own state machine, letting the
+    /// process drain in place before `numprocs` is lowered under it.
+    pub async fn signal_process(
+        &self,
+        name: &str,
+        signal: &str,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.call(
+            "supervisor.signalProcess",
+            vec![Value::String(name.to_string()), Value::String(signal.to_string())],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// The process's current Supervisor state name (`RUNNING`, `STOPPED`, ...).
+    pub async fn process_state(&self, name: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let info = self
+            .call(
+                "supervisor.getProcessInfo",
+                vec![Value::String(name.to_string())],
+            )
+            .await?;
+        match info {
+            Value::Struct(fields) => fields
+                .get("statename")
+                .and_then(|v| v.as_str())
+                .map(String::from)
+                .ok_or_else(|| "getProcessInfo response missing statename".into()),
+            _ => Err("unexpected getProcessInfo response".into()),
+        }
+    }
+
+    /// Signals a process and waits up to `timeout` for it to exit on its
+    /// own, polling its state instead of blocking Supervisor's RPC loop.
+    /// Anything still running after the timeout is force-stopped.
+    pub async fn drain_process(
+        &self,
+        name: &str,
+        signal: &str,
+        timeout: Duration,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.signal_process(name, signal).await?;
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            if matches!(
+                self.process_state(name).await.as_deref(),
+                Ok("STOPPED") | Ok("EXITED") | Ok("FATAL")
+            ) {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                break;
+            }
+            sleep(DRAIN_POLL_INTERVAL).await;
+        }
+
+        eprintln!(
+            "Process {} did not exit within {:?} of SIG{}, forcing stop",
+            name, timeout, signal
+        );
+        self.stop_process(name).await
+    }
+
+    /// Liveness probe: an endpoint that can't answer `getState` is treated
+    /// as down and skipped by `SupervisorFleet`.
+    pub async fn is_healthy(&self) -> bool {
+        self.call("supervisor.getState", vec![]).await.is_ok()
+    }
+}
+
+/// Splits `total` processes as evenly as possible across `n` endpoints,
+/// handing the remainder to the first endpoints in order.
+pub fn distribute(total: usize, n: usize) -> Vec<usize> {
+    if n == 0 {
+        return Vec::new();
+    }
+    let base = total / n;
+    let remainder = total % n;
+    (0..n).map(|i| base + usize::from(i < remainder)).collect()
+}
+
+/// A set of Supervisor endpoints that together run one logical program.
+/// The program's target `numprocs` is spread across whichever endpoints
+/// currently respond to a health check; unreachable nodes are skipped
+/// instead of failing the whole scaling decision.
+pub struct SupervisorFleet {
+    clients: Vec<SupervisorClient>,
+}
+
+impl SupervisorFleet {
+    pub fn new(endpoints: &[SupervisorEndpoint]) -> Self {
+        Self {
+            clients: endpoints.iter().map(SupervisorClient::new).collect(),
+        }
+    }
+
+    /// Returns the endpoints that currently respond to an RPC call.
+    pub async fn healthy(&self) -> Vec<&SupervisorClient> {
+        let mut healthy = Vec::new();
+        for client in &self.clients {
+            if client.is_healthy().await {
+                healthy.push(client);
+            } else {
+                eprintln!(
+                    "Supervisor endpoint {} is unreachable, skipping",
+                    client.endpoint()
+                );
+            }
+        }
+        healthy
+    }
+
+    /// Drives every healthy endpoint's copy of `program`'s `numprocs`
+    /// towards its share of `target_total`, rewriting that endpoint's own
+    /// config section, reloading it, and starting or stopping processes to
+    /// match.
+    pub async fn apply_numprocs(
+        &self,
+        program: &str,
+        current_total: usize,
+        target_total: usize,
+        stop_signal: &str,
+        stop_timeout: Duration,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let healthy = self.healthy().await;
+        if healthy.is_empty() {
+            return Err("no healthy Supervisor endpoints available".into());
+        }
+
+        let targets = distribute(target_total, healthy.len());
+
+        // Ground each endpoint's "previous" share in what its own config
+        // file actually says rather than an even split of current_total:
+        // the set of healthy endpoints can differ from the tick that last
+        // wrote current_total (a node flapped, or was just added), which
+        // would otherwise desync the assumed split from what's really
+        // running there. Fall back to the even split only if the file
+        // can't be read, e.g. on this endpoint's very first update.
+        let fallback_previous = distribute(current_total, healthy.len());
+        let mut previous = Vec::with_capacity(healthy.len());
+        for (client, &fallback) in healthy.iter().zip(fallback_previous.iter()) {
+            let prev = get_current_num_procs(client.config_path())
+                .await
+                .unwrap_or(fallback);
+            previous.push(prev);
+        }
+
+        for ((client, &target), &prev) in healthy.iter().zip(targets.iter()).zip(previous.iter()) {
+            if target == prev {
+                continue;
+            }
+
+            if target < prev {
+                for idx in target..prev {
+                    // Supervisor's default process_name template zero-pads
+                    // process_num to 2 digits (e.g. myapp_00); match it so
+                    // this targets a process that actually exists, and
+                    // propagate failures instead of silently skipping the
+                    // drain.
+                    client
+                        .drain_process(
+                            &format!("{program}:{program}_{idx:02}"),
+                            stop_signal,
+                            stop_timeout,
+                        )
+                        .await?;
+                }
+            }
+
+            update_supervisor_config(target, client.config_path()).await?;
+            client.reload_config().await?;
+
+            // reloadConfig only re-reads the file; a group that's already
+            // running has to be stopped and removed before addProcessGroup
+            // will pick up the new numprocs, or it faults ALREADY_ADDED.
+            // This necessarily restarts the whole group, not just the
+            // processes being added or removed.
+            client.stop_process_group(program).await.ok();
+            client.remove_process_group(program).await?;
+            client.add_process_group(program).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distribute_even() {
+        assert_eq!(distribute(9, 3), vec![3, 3, 3]);
+    }
+
+    #[test]
+    fn test_distribute_remainder_goes_to_first_nodes() {
+        assert_eq!(distribute(10, 3), vec![4, 3, 3]);
+    }
+
+    #[test]
+    fn test_distribute_no_healthy_nodes() {
+        assert_eq!(distribute(10, 0), Vec::<usize>::new());
+    }
+
+    #[tokio::test]
+    async fn test_supervisor_client_reload_config() {
+        // This test assumes a Supervisor instance is listening on the
+        // default inet HTTP server address and will not work in an
+        // environment where one isn't running.
+        let client = SupervisorClient::new(&SupervisorEndpoint {
+            addr: "127.0.0.1:9001".to_string(),
+            config_path: "/etc/supervisor/conf.d/example.conf".to_string(),
+        });
+        let result = client.reload_config().await;
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,13 @@
+use async_trait::async_trait;
+use std::error::Error;
+
+/// A unit of recurring background work that a `WorkerSupervisor` drives
+/// one tick at a time.
+#[async_trait]
+pub trait Worker: Send {
+    /// Performs a single unit of work (e.g. one scaling decision) and
+    /// returns the process-count target it computed and applied, or
+    /// `None` if this tick didn't act (e.g. CPU usage was too high, or
+    /// the target was unchanged).
+    async fn tick(&mut self) -> Result<Option<usize>, Box<dyn Error + Send + Sync>>;
+}